@@ -1,4 +1,10 @@
-from .engine import run_backtest, load_bt_data
+from .engine import run_backtest, load_bt_data, filter_outlier_bars
 from .batch import run_ma_grid_batch
+from .recorder import SQLiteRecorder
+from .analyzers import PortfolioRisk, TradeBlotter, PnLAttribution, TransactionCost
+from .sizers import FixedFractionalSizer, VolatilityTargetSizer, KellySizer
+from .price_limits import PriceLimitFiller, limit_pct_of, limit_prices
+from .boards import board_of, venue_of
+from .broker import AShareBroker
 
 
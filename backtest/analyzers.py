@@ -0,0 +1,306 @@
+from collections import defaultdict
+import pandas as pd
+import backtrader as bt
+
+from .boards import venue_of
+from .orders import bar_ago, new_fills
+
+
+class PortfolioRisk(bt.Analyzer):
+    """
+    组合层面风险指标（逐日计算，按账户总资产归一为百分比）：
+    - 总敞口 gross = Σ|持仓市值|，净敞口 net = Σ持仓市值
+    - 集中度：单一标的 / 单一行业的 |持仓市值| 之和占总敞口的最大比例（sector_map 为 {symbol: 行业}，未配置的标的归入自身），
+      同一行业内的多空不相互抵消
+    - 期末各行业总敞口 / 净敞口占总资产比例：last_sector_exposure_pct / last_sector_net_pct
+    - 历史 VaR：最近 var_window 个交易日的组合日收益在 (1 - var_level) 分位处的损失
+    """
+    params = dict(
+        var_level=0.95,
+        var_window=250,
+        sector_map=None,
+    )
+
+    def start(self):
+        self._rows = []
+        self._values = []
+        self._last_sector = {}
+        self._last_sector_net = {}
+
+    def next(self):
+        value = self.strategy.broker.getvalue()
+        sector_map = self.p.sector_map or {}
+        exposures = {}
+        sectors = {}
+        sectors_net = {}
+        for data in self.strategy.datas:
+            size = self.strategy.getposition(data).size
+            if not size:
+                continue
+            mv = size * float(data.close[0])
+            exposures[data._name] = mv
+            sector = sector_map.get(data._name, data._name)
+            sectors[sector] = sectors.get(sector, 0.0) + abs(mv)
+            sectors_net[sector] = sectors_net.get(sector, 0.0) + mv
+
+        gross = sum(abs(v) for v in exposures.values())
+        net = sum(exposures.values())
+        max_symbol = max((abs(v) for v in exposures.values()), default=0.0)
+        max_sector = max(sectors.values(), default=0.0)
+        self._rows.append(dict(
+            date=self.strategy.datetime.date(0),
+            value=value,
+            gross_pct=gross / value * 100 if value else None,
+            net_pct=net / value * 100 if value else None,
+            symbol_concentration_pct=max_symbol / gross * 100 if gross else 0.0,
+            sector_concentration_pct=max_sector / gross * 100 if gross else 0.0,
+        ))
+        self._values.append(value)
+        self._last_sector = {k: v / value * 100 for k, v in sectors.items()} if value else {}
+        self._last_sector_net = {k: v / value * 100 for k, v in sectors_net.items()} if value else {}
+
+    def historical_var(self):
+        """返回 (VaR 百分比, VaR 金额)，样本不足 2 天时返回 (None, None)。"""
+        values = pd.Series(self._values[-(self.p.var_window + 1):])
+        rets = values.pct_change().dropna()
+        if len(rets) < 2:
+            return None, None
+        var_pct = max(-rets.quantile(1 - self.p.var_level), 0.0) * 100
+        return var_pct, var_pct / 100 * values.iloc[-1]
+
+    def get_analysis(self):
+        df = pd.DataFrame(self._rows)
+        var_pct, var_money = self.historical_var()
+        if df.empty:
+            return dict(var_pct=var_pct, var_money=var_money)
+        return dict(
+            max_gross_pct=float(df['gross_pct'].max()),
+            avg_gross_pct=float(df['gross_pct'].mean()),
+            max_net_pct=float(df['net_pct'].max()),
+            max_symbol_concentration_pct=float(df['symbol_concentration_pct'].max()),
+            max_sector_concentration_pct=float(df['sector_concentration_pct'].max()),
+            last_sector_exposure_pct=self._last_sector,
+            last_sector_net_pct=self._last_sector_net,
+            var_level=self.p.var_level,
+            var_pct=var_pct,
+            var_money=var_money,
+            daily=df,
+        )
+
+
+class TradeBlotter(bt.Analyzer):
+    """
+    成交流水（blotter）：每笔成交一行，字段见 FIELDS。
+    - exec_time 精确到微秒（回测中为成交K线时间）
+    - order_id 为 backtrader 订单号，exec_id 为 订单号-成交序号
+    - capacity 为交易身份（默认 PRINCIPAL 自营）
+    - liquidity：只有挂单后在之后K线上按限价成交的 Limit 单记为 ADD；市价、收盘、止损单以及以优于限价成交
+      （开盘即可成交）的限价/止损限价单都在回测券商中吃单，记为 REMOVE
+    - 需要更多监管字段时继承并重写 extra_fields(order)，返回的键会追加到各行
+    csv_path 非空时 stop() 写出 CSV。
+    """
+    FIELDS = [
+        'exec_time', 'symbol', 'venue', 'order_id', 'exec_id', 'side', 'order_type',
+        'quantity', 'price', 'gross_amount', 'commission', 'capacity', 'liquidity',
+    ]
+
+    params = dict(
+        csv_path=None,
+        capacity='PRINCIPAL',
+    )
+
+    def start(self):
+        self.rows = []
+        self._exec_seq = {}
+
+    def extra_fields(self, order) -> dict:
+        return {}
+
+    @staticmethod
+    def _liquidity(order, bit) -> str:
+        rested = bit.dt > order.created.dt
+        at_limit = order.created.price is not None and abs(bit.price - order.created.price) < 1e-9
+        if order.exectype == order.Limit and rested and at_limit:
+            return 'ADD'
+        return 'REMOVE'
+
+    def notify_order(self, order):
+        symbol = order.data._name
+        for bit in new_fills(order):
+            seq = self._exec_seq.get(order.ref, 0) + 1
+            self._exec_seq[order.ref] = seq
+            row = dict(
+                exec_time=bt.num2date(bit.dt).strftime('%Y-%m-%d %H:%M:%S.%f'),
+                symbol=symbol,
+                venue=venue_of(symbol),
+                order_id=order.ref,
+                exec_id=f"{order.ref}-{seq}",
+                side='BUY' if order.isbuy() else 'SELL',
+                order_type=order.getordername(),
+                quantity=abs(bit.size),
+                price=bit.price,
+                gross_amount=abs(bit.size) * bit.price,
+                commission=bit.comm,
+                capacity=self.p.capacity,
+                liquidity=self._liquidity(order, bit),
+            )
+            row.update(self.extra_fields(order))
+            self.rows.append(row)
+
+    def to_dataframe(self) -> pd.DataFrame:
+        df = pd.DataFrame(self.rows)
+        if df.empty:
+            return pd.DataFrame(columns=self.FIELDS)
+        extra = [c for c in df.columns if c not in self.FIELDS]
+        return df[self.FIELDS + extra]
+
+    def stop(self):
+        if self.p.csv_path:
+            self.to_dataframe().to_csv(self.p.csv_path, index=False, encoding='utf-8-sig')
+
+    def get_analysis(self):
+        return dict(executions=len(self.rows), csv_path=self.p.csv_path, blotter=self.to_dataframe())
+
+
+class PnLAttribution(bt.Analyzer):
+    """
+    盈亏归因：按 策略 / 标的 / 信号标签 拆分已实现与未实现盈亏。
+    - 已实现：交易每次更新时 pnlcomm（净盈亏，含手续费）的增量，部分平仓的盈亏与开仓手续费即时计入，不必等到交易完全平仓
+    - 未实现：期末持仓按收盘价计算的浮动盈亏（不含手续费）
+    - 信号标签：下单时传 tag（如 self.buy(size=..., tag='breakout')），开仓订单的标签归属到该笔交易，未传为 untagged
+    daily 为逐日逐标的的累计盈亏与当日变动，可用作日终报告。
+    """
+
+    def start(self):
+        self._realized = defaultdict(float)
+        self._tag_realized = defaultdict(float)
+        self._last_tag = {}
+        self._trade_tag = {}
+        self._trade_pnlcomm = {}
+        self._rows = []
+
+    def notify_order(self, order):
+        if order.status not in [order.Completed]:
+            return
+        symbol = order.data._name
+        # 只有开仓/加仓（含反手）的订单标签归属到交易；平仓订单的标签（如 tag='stop'）丢弃，
+        # 避免被下一笔未带标签的开仓继承
+        pos = self.strategy.getposition(order.data).size
+        opens = pos != 0 and (pos > 0) == (order.executed.size > 0)
+        tag = order.info.get('tag')
+        if opens and tag:
+            self._last_tag[symbol] = tag
+        else:
+            self._last_tag.pop(symbol, None)
+
+    def notify_trade(self, trade):
+        symbol = trade.data._name
+        if trade.justopened:
+            self._trade_tag[trade.ref] = self._last_tag.pop(symbol, 'untagged')
+        tag = self._trade_tag.get(trade.ref, 'untagged')
+        delta = trade.pnlcomm - self._trade_pnlcomm.get(trade.ref, 0.0)
+        self._trade_pnlcomm[trade.ref] = trade.pnlcomm
+        self._realized[symbol] += delta
+        self._tag_realized[tag] += delta
+        if trade.isclosed:
+            self._trade_tag.pop(trade.ref, None)
+            self._trade_pnlcomm.pop(trade.ref, None)
+
+    def _unrealized(self, data):
+        pos = self.strategy.getposition(data)
+        return pos.size * (float(data.close[0]) - pos.price) if pos.size else 0.0
+
+    def next(self):
+        date = self.strategy.datetime.date(0)
+        for data in self.strategy.datas:
+            realized = self._realized[data._name]
+            unrealized = self._unrealized(data)
+            self._rows.append(dict(
+                date=date,
+                symbol=data._name,
+                realized=realized,
+                unrealized=unrealized,
+                total=realized + unrealized,
+            ))
+
+    def get_analysis(self):
+        strategy_name = type(self.strategy).__name__
+        daily = pd.DataFrame(self._rows, columns=['date', 'symbol', 'realized', 'unrealized', 'total'])
+        if not daily.empty:
+            daily['daily_pnl'] = daily.groupby('symbol')['total'].diff().fillna(daily['total'])
+            daily.insert(0, 'strategy', strategy_name)
+            by_instrument = daily.groupby('symbol').last()[['realized', 'unrealized', 'total']]
+        else:
+            by_instrument = pd.DataFrame(columns=['realized', 'unrealized', 'total'])
+        return dict(
+            strategy=strategy_name,
+            total=float(by_instrument['total'].sum()) if not by_instrument.empty else 0.0,
+            by_instrument=by_instrument,
+            by_tag=dict(self._tag_realized),
+            daily=daily,
+        )
+
+
+class TransactionCost(bt.Analyzer):
+    """
+    交易成本分析（TCA），每个订单一行，成本以 bp 表示，正数为不利：
+    - arrival：下单时K线收盘价（决策价）
+    - bar_vwap：成交K线的典型价 (高 + 低 + 收) / 3，日线数据下近似区间 VWAP
+    - slippage_arrival_bps / slippage_vwap_bps = 方向 * (成交均价 - 基准) / 基准 * 10000
+    - cost_arrival = 方向 * (成交均价 - 决策价) * 成交量 + 手续费
+    summary 按 venue、order_type 汇总均值与合计。
+    """
+
+    def start(self):
+        self._rows = []
+
+    def notify_order(self, order):
+        if order.status not in [order.Completed]:
+            return
+
+        data = order.data
+        size = abs(order.executed.size)
+        price = order.executed.price
+        created_ago = bar_ago(data, order.created.dt)
+        exec_ago = bar_ago(data, order.executed.dt)
+        if not size or created_ago is None or exec_ago is None:
+            return
+        arrival = float(data.close[created_ago])
+        if arrival <= 0:
+            return
+        sign = 1 if order.isbuy() else -1
+        bar_vwap = (float(data.high[exec_ago]) + float(data.low[exec_ago]) + float(data.close[exec_ago])) / 3
+        self._rows.append(dict(
+            datetime=bt.num2date(order.executed.dt),
+            symbol=data._name,
+            venue=venue_of(data._name),
+            order_id=order.ref,
+            order_type=order.getordername(),
+            side='BUY' if order.isbuy() else 'SELL',
+            quantity=size,
+            exec_price=price,
+            arrival=arrival,
+            bar_vwap=bar_vwap,
+            slippage_arrival_bps=sign * (price - arrival) / arrival * 10000,
+            slippage_vwap_bps=sign * (price - bar_vwap) / bar_vwap * 10000 if bar_vwap else None,
+            commission=order.executed.comm,
+            cost_arrival=sign * (price - arrival) * size + order.executed.comm,
+        ))
+
+    def get_analysis(self):
+        orders = pd.DataFrame(self._rows)
+        if orders.empty:
+            return dict(orders=orders, summary=pd.DataFrame(), avg_slippage_arrival_bps=None, total_cost=0.0)
+        summary = orders.groupby(['venue', 'order_type']).agg(
+            orders=('order_id', 'count'),
+            avg_slippage_arrival_bps=('slippage_arrival_bps', 'mean'),
+            avg_slippage_vwap_bps=('slippage_vwap_bps', 'mean'),
+            total_commission=('commission', 'sum'),
+            total_cost=('cost_arrival', 'sum'),
+        )
+        return dict(
+            orders=orders,
+            summary=summary,
+            avg_slippage_arrival_bps=float(orders['slippage_arrival_bps'].mean()),
+            total_cost=float(orders['cost_arrival'].sum()),
+        )
@@ -0,0 +1,37 @@
+from typing import Tuple
+
+
+# 代码前缀 -> (交易所, 板块)，按前缀从长到短匹配
+_BOARD_PREFIXES = [
+    ('688', 'SSE', 'STAR'),
+    ('689', 'SSE', 'STAR'),
+    ('900', 'SSE', 'B'),
+    ('920', 'BSE', 'BSE'),
+    ('300', 'SZSE', 'CHINEXT'),
+    ('301', 'SZSE', 'CHINEXT'),
+    ('200', 'SZSE', 'B'),
+    ('6', 'SSE', 'MAIN'),
+    ('0', 'SZSE', 'MAIN'),
+    ('4', 'BSE', 'BSE'),
+    ('8', 'BSE', 'BSE'),
+]
+
+
+def board_of(symbol: str) -> Tuple[str, str]:
+    """
+    按 A 股代码前缀推断 (交易所, 板块)：
+    - 上交所：688/689 科创板 STAR，900 B 股，其余 6 开头主板
+    - 深交所：300/301 创业板 CHINEXT，200 B 股，0 开头主板
+    - 北交所：920、4、8 开头
+    无法识别时返回 ('UNKNOWN', 'UNKNOWN')。
+    """
+    code = str(symbol).split('.')[0]
+    for prefix, venue, board in _BOARD_PREFIXES:
+        if code.startswith(prefix):
+            return venue, board
+    return 'UNKNOWN', 'UNKNOWN'
+
+
+def venue_of(symbol: str) -> str:
+    """交易所：SSE / SZSE / BSE / UNKNOWN。"""
+    return board_of(symbol)[0]
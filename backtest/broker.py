@@ -0,0 +1,89 @@
+from collections import defaultdict
+import backtrader as bt
+
+from .price_limits import PriceLimitFiller
+
+
+class AShareBroker(bt.brokers.BackBroker):
+    """
+    回测券商：在 BackBroker 基础上于下单（submit）时做 A 股检查，未配置对应项时与 BackBroker 一致。
+    - 涨跌停：filler 为 PriceLimitFiller 且按收盘撮合时，下单K线收盘价已触及涨跌停的市价单改为下一根K线开盘价撮合
+      （等到撮合时才拦截会多顺延一根K线，见 PriceLimitFiller）
+    - max_gross_pct：事前总敞口上限（占总资产比例，如 0.8）。按当前持仓加未成交订单、以当前收盘价估算，
+      会使 Σ|持仓市值| 增加并超过上限的订单直接拒单（Rejected），减仓、平仓不受限；None 不限制。
+      所有策略的订单都经过这里，无论是否使用 Sizer；gross_rejected 为因此拒绝的订单数
+    - shortable：可融券卖空的代码集合（融券标的/券源表）。非空时，对不在表中的标的新开或加大空头的订单，
+      short_check='reject' 拒单，'warn' 仅打印提示照常撮合；平多、回补不受限；None 不检查。
+      short_violations 为不在表中的卖空订单数（两种模式都计数）
+    """
+    params = dict(
+        max_gross_pct=None,
+        shortable=None,
+        short_check='reject',
+    )
+
+    def start(self):
+        super(AShareBroker, self).start()
+        self.gross_rejected = 0
+        self.short_violations = 0
+        self._shortable = None if self.p.shortable is None else {str(s).split('.')[0] for s in self.p.shortable}
+
+    @staticmethod
+    def _signed(order, size):
+        return abs(size) if order.isbuy() else -abs(size)
+
+    def _projected_sizes(self):
+        # 持仓 + 已提交未成交部分，同一根K线内先后下的多笔订单（如配对的两条腿）合并计算
+        sizes = defaultdict(float)
+        for data, position in self.positions.items():
+            sizes[data] += position.size
+        for order in list(self.submitted) + list(self.pending):
+            if order.alive():
+                sizes[order.data] += self._signed(order, order.executed.remsize)
+        return sizes
+
+    @staticmethod
+    def _gross(sizes):
+        return sum(abs(size) * float(data.close[0]) for data, size in sizes.items() if size)
+
+    def gross_room(self):
+        """总敞口上限下还可新增的市值，未设置上限时返回 None。"""
+        if self.p.max_gross_pct is None:
+            return None
+        return self.getvalue() * self.p.max_gross_pct - self._gross(self._projected_sizes())
+
+    def _exceeds_gross(self, order):
+        if self.p.max_gross_pct is None:
+            return False
+        sizes = self._projected_sizes()
+        before = self._gross(sizes)
+        sizes[order.data] += self._signed(order, order.created.size)
+        after = self._gross(sizes)
+        return after > before and after > self.getvalue() * self.p.max_gross_pct
+
+    def _unshortable(self, order):
+        if self._shortable is None or order.isbuy():
+            return False
+        before = self._projected_sizes()[order.data]
+        after = before + self._signed(order, order.created.size)
+        return after < min(before, 0) and str(order.data._name).split('.')[0] not in self._shortable
+
+    def submit(self, order, check=True):
+        if self._unshortable(order):
+            self.short_violations += 1
+            if self.p.short_check == 'reject':
+                order.reject(self)
+                self.notify(order)
+                return order
+            print(f"{order.data.datetime.date(0)} 卖空 {order.data._name} 不在可融券列表中")
+        if self._exceeds_gross(order):
+            self.gross_rejected += 1
+            order.reject(self)
+            self.notify(order)
+            return order
+        filler = self.p.filler
+        if self.p.coc and isinstance(filler, PriceLimitFiller) and filler.uses_coc(order):
+            # 下单时数据停在下单K线，ago=0 即该K线
+            if filler.hits_limit(order, order.created.pclose, 0):
+                order.info['coc'] = False
+        return super(AShareBroker, self).submit(order, check)
@@ -1,5 +1,6 @@
 import os
 import sys
+from typing import Tuple
 import pandas as pd
 import backtrader as bt
 
@@ -8,8 +9,71 @@ CURRENT_DIR = os.path.dirname(os.path.dirname(__file__))
 sys.path.append(os.path.join(CURRENT_DIR, 'mongo_py'))
 from stock_data import get_stock_daily_data
 
+from .recorder import SQLiteRecorder
+from .analyzers import PortfolioRisk, TradeBlotter, PnLAttribution, TransactionCost
+from .price_limits import PriceLimitFiller
+from .broker import AShareBroker
+from .boards import board_of
 
-def load_bt_data(symbol: str, start_date: str, end_date: str) -> bt.feeds.PandasData:
+
+_RESAMPLE_TIMEFRAMES = {
+    'weekly': bt.TimeFrame.Weeks,
+    'monthly': bt.TimeFrame.Months,
+}
+
+
+def filter_outlier_bars(
+    df: pd.DataFrame,
+    max_jump_pct: float = None,
+    check_invalid: bool = True,
+) -> Tuple[pd.DataFrame, pd.DataFrame]:
+    """
+    异常K线过滤（输入为已数值化、列名为 Open/High/Low/Close 的 DataFrame）：
+    - non_positive_price：开高低收任一非正（check_invalid=True 时检查）
+    - high_below_low：最高价低于最低价（check_invalid=True 时检查）
+    - price_jump：max_jump_pct 非空时，收盘价相对前后两根有效K线的涨跌幅均超过该百分比（孤立尖刺）；
+      首尾K线只有一侧相邻，无法与真实跳空区分，不做尖刺判断
+    返回：(过滤后 df, 被过滤明细 df，含 reason 列用于审计)
+    """
+    reasons = pd.Series('', index=range(len(df)), dtype=object)
+    prices = df[['Open', 'High', 'Low', 'Close']].to_numpy()
+    if check_invalid:
+        reasons[(prices <= 0).any(axis=1)] = 'non_positive_price'
+        reasons[(reasons == '') & (df['High'].to_numpy() < df['Low'].to_numpy())] = 'high_below_low'
+
+    if max_jump_pct is not None:
+        valid = reasons[reasons == ''].index
+        closes = pd.Series(df['Close'].to_numpy()[valid], index=valid)
+        jump_prev = (closes / closes.shift(1) - 1).abs() * 100
+        jump_next = (closes / closes.shift(-1) - 1).abs() * 100
+        spikes = closes.index[(jump_prev > max_jump_pct) & (jump_next > max_jump_pct)]
+        reasons[spikes] = 'price_jump'
+
+    bad = (reasons != '').to_numpy()
+    audit = df[bad].copy()
+    audit['reason'] = reasons[bad].to_numpy()
+    return df[~bad], audit
+
+
+def _append_audit(audit_csv: str, symbol: str, audit: pd.DataFrame) -> None:
+    # 参数网格会对同一标的重复加载数据，按 (symbol, datetime, reason) 去重后整体重写
+    audit = audit.reset_index()
+    audit.insert(0, 'symbol', symbol)
+    if os.path.exists(audit_csv):
+        existing = pd.read_csv(audit_csv, dtype={'symbol': str}, parse_dates=['datetime'])
+        audit = pd.concat([existing, audit], ignore_index=True)
+    audit = audit.drop_duplicates(subset=['symbol', 'datetime', 'reason'], keep='first')
+    audit.to_csv(audit_csv, index=False, encoding='utf-8-sig')
+
+
+def load_bt_data(
+    symbol: str,
+    start_date: str,
+    end_date: str,
+    filter_bars: bool = False,
+    max_jump_pct: float = None,
+    audit_csv: str = None,
+) -> bt.feeds.PandasData:
     df = get_stock_daily_data(symbol, start_date, end_date)
     print(f"loaded rows: {len(df)}; head:\n{df.head(3)}")
     cols = ['datetime', 'open', 'high', 'low', 'close', 'volume']
@@ -27,6 +91,12 @@ def load_bt_data(symbol: str, start_date: str, end_date: str) -> bt.feeds.Pandas
     for c in ['Open', 'High', 'Low', 'Close', 'Volume']:
         df[c] = pd.to_numeric(df[c], errors='coerce')
     df = df.dropna(subset=['Open', 'High', 'Low', 'Close'])
+    if filter_bars or max_jump_pct is not None:
+        df, audit = filter_outlier_bars(df, max_jump_pct=max_jump_pct, check_invalid=filter_bars)
+        if not audit.empty:
+            print(f"filtered bars: {len(audit)}; {audit['reason'].value_counts().to_dict()}")
+            if audit_csv:
+                _append_audit(audit_csv, symbol, audit)
     data_feed = bt.feeds.PandasData(dataname=df)
     return data_feed
 
@@ -41,34 +111,93 @@ def run_backtest(
     commission: float = 0.001,
     cheat_on_close: bool = True,
     add_analyzers: bool = True,
+    extra_analyzers: bool = False,
     verbose: bool = True,
+    db_path: str = None,
+    run_id: str = None,
+    filter_bars: bool = False,
+    max_jump_pct: float = None,
+    audit_csv: str = None,
+    sector_map: dict = None,
+    resample: str = None,
+    sizer_cls=None,
+    sizer_params=None,
+    blotter_csv: str = None,
+    price_limit: bool = False,
+    st_symbols=None,
+    max_gross_pct: float = None,
+    shortable=None,
+    short_check: str = 'reject',
 ):
     """
     通用回测封装：
-    - 支持单/多标的
+    - 支持单/多标的（仅人民币计价的 A 股；B 股以美元/港币计价，组合不做汇率换算，传入时报错）
     - 可配置策略与参数、初始资金、手续费、是否按收盘价撮合
     - 可选添加常用分析器并返回结果
+    - 可选将订单/成交/持仓/每日盈亏写入 SQLite（db_path 非空时启用）
+    - 可选过滤异常K线：filter_bars 剔除非正价格/高低价倒挂，max_jump_pct 剔除孤立尖刺，audit_csv 记录被过滤明细
+    - extra_analyzers=True 时另加风险（敞口/集中度/VaR）、盈亏归因、交易成本分析器，sector_map 为 {symbol: 行业} 用于行业集中度
+    - resample='weekly'/'monthly' 时将日线聚合为周线/月线后回测
+    - 可选仓位计算器 sizer_cls/sizer_params（见 backtest/sizers.py），策略不指定 size 下单时生效
+    - 可选导出逐笔成交流水（blotter_csv 非空时启用）
+    - price_limit=True 时模拟涨跌停：涨停不能买入、跌停不能卖出，st_symbols 为按 5% 限制的 ST 股
+    - max_gross_pct 非空时在券商下单环节执行事前总敞口上限（占总资产比例），对所有策略的订单生效
+    - shortable 非空时为可融券标的表，不在表中的卖空开仓按 short_check 拒单（'reject'）或仅提示（'warn'）
     返回：dict，包含 cerebro、results、final_value、analyzers（如启用）
     """
     strategy_params = strategy_params or {}
+    if strategy_params.get('use_sizer') and sizer_cls is None:
+        # 未配置时 backtrader 默认 FixedSize(stake=1)，只会买 1 股
+        raise ValueError("use_sizer=True requires sizer_cls")
+    if short_check not in ('reject', 'warn'):
+        raise ValueError(f"unsupported short_check: {short_check}")
+    if resample and resample not in _RESAMPLE_TIMEFRAMES:
+        raise ValueError(f"unsupported resample: {resample}")
+    if resample and price_limit:
+        # 涨跌停按日线前收盘计算，聚合后的前收盘是上周/上月收盘，无法正确判断
+        raise ValueError("price_limit requires daily bars; do not combine it with resample")
+    symbols_list = symbols if isinstance(symbols, (list, tuple)) else [symbols]
+    b_shares = [s for s in symbols_list if board_of(s)[1] == 'B']
+    if b_shares:
+        raise ValueError(f"B-share symbols are not supported (USD/HKD priced, no FX conversion): {b_shares}")
 
     cerebro = bt.Cerebro()
     cerebro.addstrategy(strategy_cls, **strategy_params)
+    if sizer_cls is not None:
+        cerebro.addsizer(sizer_cls, **(sizer_params or {}))
 
-    symbols_list = symbols if isinstance(symbols, (list, tuple)) else [symbols]
     for s in symbols_list:
-        data_feed = load_bt_data(s, start_date, end_date)
-        cerebro.adddata(data_feed, name=str(s))
+        data_feed = load_bt_data(
+            s, start_date, end_date, filter_bars=filter_bars, max_jump_pct=max_jump_pct, audit_csv=audit_csv,
+        )
+        if resample:
+            cerebro.resampledata(data_feed, name=str(s), timeframe=_RESAMPLE_TIMEFRAMES[resample])
+        else:
+            cerebro.adddata(data_feed, name=str(s))
 
+    cerebro.broker = AShareBroker(max_gross_pct=max_gross_pct, shortable=shortable, short_check=short_check)
     cerebro.broker.setcash(initial_cash)
     cerebro.broker.setcommission(commission=commission)
     cerebro.broker.set_coc(cheat_on_close)
+    limit_filler = None
+    if price_limit:
+        limit_filler = PriceLimitFiller(coc=cheat_on_close, st_symbols=st_symbols or ())
+        cerebro.broker.set_filler(limit_filler)
 
     if add_analyzers:
         cerebro.addanalyzer(bt.analyzers.SharpeRatio, _name='sharpe', timeframe=bt.TimeFrame.Days)
         cerebro.addanalyzer(bt.analyzers.DrawDown, _name='drawdown')
         cerebro.addanalyzer(bt.analyzers.Returns, _name='returns')
         cerebro.addanalyzer(bt.analyzers.TradeAnalyzer, _name='trades')
+    if extra_analyzers:
+        cerebro.addanalyzer(PortfolioRisk, _name='risk', sector_map=sector_map)
+        cerebro.addanalyzer(PnLAttribution, _name='attribution')
+        cerebro.addanalyzer(TransactionCost, _name='tca')
+
+    if db_path:
+        cerebro.addanalyzer(SQLiteRecorder, _name='recorder', db_path=db_path, run_id=run_id)
+    if blotter_csv:
+        cerebro.addanalyzer(TradeBlotter, _name='blotter', csv_path=blotter_csv)
 
     if verbose:
         print('初始资金:', round(cerebro.broker.getvalue(), 2))
@@ -78,20 +207,40 @@ def run_backtest(
 
     if verbose:
         print('结束资金:', final_value)
+        if limit_filler is not None and limit_filler.blocked:
+            print(f"涨跌停未成交次数: {limit_filler.blocked}")
+        if cerebro.broker.gross_rejected:
+            print(f"超总敞口上限拒单: {cerebro.broker.gross_rejected}")
+        if cerebro.broker.short_violations:
+            action = '拒单' if short_check == 'reject' else '提示'
+            print(f"不可融券卖空{action}: {cerebro.broker.short_violations}")
 
     analyzers = {}
-    if add_analyzers and results:
+    if (add_analyzers or extra_analyzers) and results:
         strat = results[0]
         analyzers = {
             'sharpe': strat.analyzers.sharpe.get_analysis() if hasattr(strat.analyzers, 'sharpe') else {},
             'drawdown': strat.analyzers.drawdown.get_analysis() if hasattr(strat.analyzers, 'drawdown') else {},
             'returns': strat.analyzers.returns.get_analysis() if hasattr(strat.analyzers, 'returns') else {},
             'trades': strat.analyzers.trades.get_analysis() if hasattr(strat.analyzers, 'trades') else {},
+            'risk': strat.analyzers.risk.get_analysis() if hasattr(strat.analyzers, 'risk') else {},
+            'attribution': strat.analyzers.attribution.get_analysis() if hasattr(strat.analyzers, 'attribution') else {},
+            'tca': strat.analyzers.tca.get_analysis() if hasattr(strat.analyzers, 'tca') else {},
         }
 
         if verbose:
             _print_analyzer_summary(analyzers)
 
+    if blotter_csv and results:
+        analyzers['blotter'] = results[0].analyzers.blotter.get_analysis()
+        if verbose:
+            print(f"成交流水已导出: {blotter_csv} ({analyzers['blotter']['executions']} 笔)")
+
+    if db_path and results:
+        analyzers['recorder'] = results[0].analyzers.recorder.get_analysis()
+        if verbose:
+            print(f"回测记录已写入: {db_path} (run_id={analyzers['recorder']['run_id']})")
+
     return {
         'cerebro': cerebro,
         'results': results,
@@ -114,6 +263,8 @@ def _print_analyzer_summary(analyzers: dict) -> None:
     drawdown = analyzers.get('drawdown', {})
     returns = analyzers.get('returns', {})
     trades = analyzers.get('trades', {})
+    risk = analyzers.get('risk', {})
+    tca = analyzers.get('tca', {})
 
     sharpe_ratio = sharpe.get('sharperatio', None)
 
@@ -170,4 +321,12 @@ def _print_analyzer_summary(analyzers: dict) -> None:
     if payoff is not None:
         print(f"盈亏比: {payoff:.2f}")
 
+    if risk.get('max_gross_pct') is not None:
+        print(f"最大总敞口: {risk['max_gross_pct']:.2f}%, 最大单标的集中度: {risk['max_symbol_concentration_pct']:.2f}%")
+    if risk.get('var_pct') is not None:
+        print(f"历史VaR({risk['var_level'] * 100:.0f}%): {risk['var_pct']:.2f}% / {risk['var_money']:.2f}")
+
+    if tca.get('avg_slippage_arrival_bps') is not None:
+        print(f"平均滑点(相对决策价): {tca['avg_slippage_arrival_bps']:.2f}bp, 交易成本合计: {tca['total_cost']:.2f}")
+
 
@@ -0,0 +1,19 @@
+def new_fills(order):
+    """
+    本次通知新增的成交（backtrader 的 OrderExecutionBit 序列）：
+    通知中的订单是克隆，iterpending() 仅包含自上次通知以来的成交；非部分成交/完成的通知返回空。
+    """
+    if order.status not in [order.Partial, order.Completed]:
+        return ()
+    return order.executed.iterpending()
+
+
+def bar_ago(data, dt_num, start=0):
+    """
+    从 ago=start 向前回溯，返回第一根时间不晚于 dt_num 的K线的 ago，数据不足时返回 None。
+    订单通知与撮合可能晚于下单/成交K线（如按收盘撮合），据此定位对应K线。
+    """
+    for ago in range(start, -len(data), -1):
+        if data.datetime[ago] <= dt_num:
+            return ago
+    return None
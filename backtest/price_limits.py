@@ -0,0 +1,109 @@
+from decimal import Decimal, ROUND_HALF_UP
+from typing import Dict, Iterable, Tuple
+
+from .boards import board_of
+from .orders import bar_ago
+
+
+# 各板块涨跌幅限制比例，未识别的板块按主板处理
+_BOARD_LIMIT_PCT = {
+    'MAIN': 0.10,
+    'B': 0.10,
+    'STAR': 0.20,
+    'CHINEXT': 0.20,
+    'BSE': 0.30,
+}
+
+
+def limit_pct_of(symbol: str, st_symbols: Iterable[str] = (), limit_pct_map: Dict[str, float] = None) -> float:
+    """
+    A 股涨跌幅限制比例：
+    - limit_pct_map 中显式配置的优先
+    - ST 股 5%
+    - 其余按板块（见 backtest/boards.py）：主板/B 股 10%，科创板/创业板 20%，北交所 30%
+    """
+    code = str(symbol).split('.')[0]
+    if limit_pct_map and code in limit_pct_map:
+        return limit_pct_map[code]
+    if code in set(st_symbols):
+        return 0.05
+    return _BOARD_LIMIT_PCT.get(board_of(code)[1], 0.10)
+
+
+def _round_half_up(value: Decimal, tick: float = 0.01) -> float:
+    # 交易所按四舍五入取到分，Python round() 为银行家舍入，两者在 .xx5 处不一致
+    return float(value.quantize(Decimal(str(tick)), rounding=ROUND_HALF_UP))
+
+
+def limit_prices(prev_close: float, pct: float, tick: float = 0.01) -> Tuple[float, float]:
+    """按前收盘价计算 (跌停价, 涨停价)，四舍五入到最小价位 tick。"""
+    # 乘积也用 Decimal 计算：浮点下 1.65 * 0.9 = 1.4849999...，舍入前 .xx5 已经丢失
+    close = Decimal(str(prev_close))
+    rate = Decimal(str(pct))
+    return _round_half_up(close * (1 - rate), tick), _round_half_up(close * (1 + rate), tick)
+
+
+class PriceLimitFiller(object):
+    """
+    涨跌停撮合限制（backtrader broker filler）：
+    - 成交价达到涨停价的买单、达到跌停价的卖单本根K线不成交，订单保留到下一根K线继续尝试
+    - 按收盘价撮合（coc）的市价单以下单K线（t）的收盘价判断。backtrader 在 t+1 的撮合轮次才成交 coc 单，
+      配合 backtest/broker.py 的 AShareBroker 时在下单时即判断，触及涨跌停则关闭该单 coc（order.info['coc'] = False），
+      在 t+1 开盘价撮合；只用 filler 时要到 t+1 撮合才拦截，关闭 coc 后在 t+2 开盘价撮合
+    - 前收盘价取判断K线的上一根收盘，首根K线不做限制
+    - 判断留一个最小价位 tick 的容差：前复权价格常与计算出的涨跌停价相差一分
+    - blocked 为被拦截过的订单数（同一订单多次拦截只计一次）
+    用法：cerebro.broker = AShareBroker(); cerebro.broker.set_filler(PriceLimitFiller(coc=True))
+    """
+
+    def __init__(
+        self,
+        coc: bool = True,
+        st_symbols: Iterable[str] = (),
+        limit_pct_map: Dict[str, float] = None,
+        tick: float = 0.01,
+    ):
+        self.coc = coc
+        self.tick = tick
+        self.st_symbols = set(st_symbols)
+        self.limit_pct_map = limit_pct_map or {}
+        self._blocked_refs = set()
+
+    @property
+    def blocked(self) -> int:
+        return len(self._blocked_refs)
+
+    def uses_coc(self, order) -> bool:
+        return self.coc and order.exectype == order.Market and order.info.get('coc', True)
+
+    def _bar_ago(self, order, ago):
+        if not self.uses_coc(order):
+            return ago
+        # 按收盘撮合时成交价来自下单K线，定位该K线
+        return bar_ago(order.data, order.created.dt, start=ago)
+
+    def hits_limit(self, order, price, bar_ago) -> bool:
+        """
+        price 是否触及 bar_ago 所在K线的涨停（买单）/跌停（卖单）；触及时记入 blocked。
+        """
+        data = order.data
+        if bar_ago is None or len(data) + bar_ago < 2:
+            return False
+        prev_close = float(data.close[bar_ago - 1])
+        if prev_close <= 0:
+            return False
+        pct = limit_pct_of(data._name, self.st_symbols, self.limit_pct_map)
+        limit_down, limit_up = limit_prices(prev_close, pct, self.tick)
+        # 留 1 个 tick 并加微小 eps，避免浮点误差与复权价偏差导致漏判
+        tolerance = self.tick + 1e-9
+        if (order.isbuy() and price >= limit_up - tolerance) or (order.issell() and price <= limit_down + tolerance):
+            self._blocked_refs.add(order.ref)
+            return True
+        return False
+
+    def __call__(self, order, price, ago):
+        if self.hits_limit(order, price, self._bar_ago(order, ago)):
+            if self.uses_coc(order):
+                order.info['coc'] = False
+            return 0
+        return abs(order.executed.remsize)
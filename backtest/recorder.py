@@ -0,0 +1,100 @@
+import sqlite3
+import pandas as pd
+import backtrader as bt
+
+from .orders import new_fills
+
+
+_SCHEMA = [
+    """
+    CREATE TABLE IF NOT EXISTS orders (
+        run_id TEXT, datetime TEXT, ref INTEGER, symbol TEXT, side TEXT,
+        status TEXT, size REAL, price REAL
+    )
+    """,
+    """
+    CREATE TABLE IF NOT EXISTS fills (
+        run_id TEXT, datetime TEXT, ref INTEGER, symbol TEXT, side TEXT,
+        size REAL, price REAL, value REAL, commission REAL
+    )
+    """,
+    """
+    CREATE TABLE IF NOT EXISTS positions (
+        run_id TEXT, date TEXT, symbol TEXT, size REAL, price REAL
+    )
+    """,
+    """
+    CREATE TABLE IF NOT EXISTS daily_pnl (
+        run_id TEXT, date TEXT, cash REAL, value REAL, pnl REAL
+    )
+    """,
+]
+
+
+class SQLiteRecorder(bt.Analyzer):
+    """
+    将回测中的订单、成交、每日持仓与每日资金/盈亏写入 SQLite，便于事后用 SQL 查询。
+    - 记录先缓存在内存，stop() 时一次事务写入，避免拖慢回测
+    - 每次回测带 run_id，多次回测可写入同一个库
+    """
+    params = dict(
+        db_path='backtest_records.db',
+        run_id=None,
+    )
+
+    def start(self):
+        self.run_id = self.p.run_id or pd.Timestamp.now().strftime('%Y%m%d_%H%M%S_%f')
+        self._orders = []
+        self._fills = []
+        self._positions = []
+        self._daily = []
+        self._prev_value = self.strategy.broker.getvalue()
+
+    def notify_order(self, order):
+        if order.status in [order.Submitted]:
+            return
+        dt = self.strategy.datetime.datetime(0).isoformat()
+        side = 'BUY' if order.isbuy() else 'SELL'
+        symbol = order.data._name
+        self._orders.append((
+            self.run_id, dt, order.ref, symbol, side,
+            order.getstatusname(), order.created.size, order.created.price,
+        ))
+        for bit in new_fills(order):
+            self._fills.append((
+                self.run_id, bt.num2date(bit.dt).isoformat(), order.ref, symbol, side,
+                bit.size, bit.price, bit.value, bit.comm,
+            ))
+
+    def next(self):
+        date = self.strategy.datetime.date(0).isoformat()
+        for data in self.strategy.datas:
+            pos = self.strategy.getposition(data)
+            if pos.size:
+                self._positions.append((self.run_id, date, data._name, pos.size, pos.price))
+        value = self.strategy.broker.getvalue()
+        cash = self.strategy.broker.get_cash()
+        self._daily.append((self.run_id, date, cash, value, value - self._prev_value))
+        self._prev_value = value
+
+    def stop(self):
+        conn = sqlite3.connect(self.p.db_path)
+        try:
+            with conn:
+                for ddl in _SCHEMA:
+                    conn.execute(ddl)
+                conn.executemany('INSERT INTO orders VALUES (?, ?, ?, ?, ?, ?, ?, ?)', self._orders)
+                conn.executemany('INSERT INTO fills VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)', self._fills)
+                conn.executemany('INSERT INTO positions VALUES (?, ?, ?, ?, ?)', self._positions)
+                conn.executemany('INSERT INTO daily_pnl VALUES (?, ?, ?, ?, ?)', self._daily)
+        finally:
+            conn.close()
+
+    def get_analysis(self):
+        return dict(
+            run_id=self.run_id,
+            db_path=self.p.db_path,
+            orders=len(self._orders),
+            fills=len(self._fills),
+            days=len(self._daily),
+        )
@@ -0,0 +1,99 @@
+import math
+import numpy as np
+import backtrader as bt
+
+from .broker import AShareBroker
+
+
+class AllocatedSizer(bt.Sizer):
+    """
+    仓位计算基类：
+    - capital_pct：该策略可用的资产比例（资金分配），买入预算 = 资产 * capital_pct - 该标的已有持仓市值，且不超过可用现金的 99%
+    - 券商为 AShareBroker 且设置了 max_gross_pct 时，买入预算不超过总敞口上限的剩余额度（先缩减下单量，而不是被券商拒单）
+    - lot：每手股数（A 股 100），下单量向下取整到整手
+    - 卖出时若持有多头则全部平仓，无多头时返回 0（不计算卖空开仓）；买入时若持有空头则全部回补
+    子类实现 target_weight(data)，返回预算中实际投入的比例。
+    """
+    params = dict(
+        capital_pct=1.0,
+        lot=100,
+    )
+
+    def target_weight(self, data):
+        raise NotImplementedError
+
+    def _getsizing(self, comminfo, cash, data, isbuy):
+        position = self.strategy.getposition(data)
+        if not isbuy:
+            return position.size if position.size > 0 else 0
+        if position.size < 0:
+            return -position.size
+
+        price = float(data.close[0])
+        if price <= 0:
+            return 0
+        value = self.broker.getvalue()
+        held = position.size * price
+        budget = value * self.p.capital_pct - held
+        budget = min(budget, cash * 0.99)
+        if isinstance(self.broker, AShareBroker):
+            room = self.broker.gross_room()
+            if room is not None:
+                budget = min(budget, room)
+        weight = self.target_weight(data)
+        if budget <= 0 or not weight or weight <= 0:
+            return 0
+        size = int(budget * weight / price)
+        return size // self.p.lot * self.p.lot
+
+
+class FixedFractionalSizer(AllocatedSizer):
+    """固定比例：每次投入预算的 fraction。"""
+    params = dict(
+        fraction=0.99,
+    )
+
+    def target_weight(self, data):
+        return self.p.fraction
+
+
+class VolatilityTargetSizer(AllocatedSizer):
+    """
+    波动率目标：权重 = target_vol / 近 vol_period 日年化波动率，上限 max_weight。
+    历史不足时不下单。
+    """
+    params = dict(
+        target_vol=0.15,
+        vol_period=20,
+        max_weight=1.0,
+        annual_days=252,
+    )
+
+    def target_weight(self, data):
+        closes = np.asarray(data.close.get(size=self.p.vol_period + 1), dtype=float)
+        if len(closes) < self.p.vol_period + 1 or (closes <= 0).any():
+            return 0.0
+        vol = np.diff(np.log(closes)).std() * math.sqrt(self.p.annual_days)
+        if vol <= 0:
+            return self.p.max_weight
+        return min(self.p.target_vol / vol, self.p.max_weight)
+
+
+class KellySizer(AllocatedSizer):
+    """
+    凯利公式（带折扣与上限）：f = W - (1 - W) / R，W 为胜率，R 为盈亏比；
+    实际权重 = min(f * kelly_scale, max_weight)，f <= 0 时不下单。
+    win_rate / payoff 可取自历史回测结果（胜率、盈亏比）。
+    """
+    params = dict(
+        win_rate=0.5,
+        payoff=1.5,
+        kelly_scale=0.5,
+        max_weight=0.25,
+    )
+
+    def target_weight(self, data):
+        if self.p.payoff <= 0:
+            return 0.0
+        kelly = self.p.win_rate - (1 - self.p.win_rate) / self.p.payoff
+        return min(kelly * self.p.kelly_scale, self.p.max_weight)
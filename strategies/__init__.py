@@ -1,4 +1,7 @@
 # Expose strategies
 from .dual_ma import DualMovingAverageStrategy
+from .pairs import PairsTradingStrategy
+from .breakout import DonchianBreakoutStrategy
+from .indicators import ParkinsonVolatility, BipowerVolatility, EWMAVolatility
 
 
@@ -0,0 +1,68 @@
+import backtrader as bt
+
+
+class DonchianBreakoutStrategy(bt.Strategy):
+    """
+    唐奇安通道突破（海龟式）：
+    - 收盘价突破前 entry_period 根K线最高价时开多
+    - 收盘价跌破前 exit_period 根K线最低价，或跌破 入场价 - stop_atr * ATR 时平仓
+    - 仓位按风险计算：单笔止损金额 = 账户资产 * risk_pct，股数 = 止损金额 / (stop_atr * ATR)，
+      且不超过可用现金的 99%
+    可配合 run_backtest(resample='weekly') 在周线上运行。
+    """
+    params = dict(
+        entry_period=20,
+        exit_period=10,
+        atr_period=20,
+        stop_atr=2.0,
+        risk_pct=0.01,
+        printlog=False,
+    )
+
+    def __init__(self):
+        # 用前一根K线的通道，避免当前K线自身参与比较
+        self.upper = bt.indicators.Highest(self.data.high(-1), period=self.params.entry_period)
+        self.lower = bt.indicators.Lowest(self.data.low(-1), period=self.params.exit_period)
+        self.atr = bt.indicators.ATR(self.data, period=self.params.atr_period)
+        self.order = None
+        self.stop_price = None
+
+    def log(self, txt):
+        if self.params.printlog:
+            dt = self.data.datetime.date(0)
+            print(f"{dt} - {txt}")
+
+    def notify_order(self, order):
+        if order.status in [order.Submitted, order.Accepted]:
+            return
+        if order.status in [order.Completed]:
+            action = 'BUY' if order.isbuy() else 'SELL'
+            self.log(f"ORDER {action} EXECUTED, price={order.executed.price:.4f}, size={order.executed.size}")
+            if order.isbuy():
+                self.stop_price = order.executed.price - self.params.stop_atr * float(self.atr[0])
+        elif order.status in [order.Canceled, order.Margin, order.Rejected]:
+            self.log(f"ORDER {order.getstatusname()}")
+        self.order = None
+
+    def notify_trade(self, trade):
+        if trade.isclosed:
+            self.log(f"TRADE PnL gross={trade.pnl:.2f} net={trade.pnlcomm:.2f}")
+
+    def next(self):
+        if self.order:
+            return
+
+        close = float(self.data.close[0])
+        if not self.position:
+            if close > self.upper[0]:
+                risk_per_share = self.params.stop_atr * float(self.atr[0])
+                if risk_per_share <= 0:
+                    return
+                size = int(self.broker.getvalue() * self.params.risk_pct / risk_per_share)
+                size = min(size, int(self.broker.get_cash() * 0.99 / close))
+                if size > 0:
+                    self.log(f"BREAKOUT close={close:.4f} upper={float(self.upper[0]):.4f} atr={float(self.atr[0]):.4f}")
+                    self.order = self.buy(size=size)
+        else:
+            if close < self.lower[0] or (self.stop_price is not None and close < self.stop_price):
+                self.order = self.close()
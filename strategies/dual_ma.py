@@ -6,6 +6,7 @@ class DualMovingAverageStrategy(bt.Strategy):
         fast=5,
         slow=20,
         printlog=False,
+        use_sizer=False,
     )
 
     def __init__(self):
@@ -46,10 +47,14 @@ class DualMovingAverageStrategy(bt.Strategy):
 
         if not self.position:
             if ready and self.sma_fast[0] > self.sma_slow[0]:
-                # 使用 99% 现金计算可买股数，预留手续费避免保证金不足
-                cash = self.broker.get_cash()
-                price = float(self.data.close[0])
-                size = int((cash * 0.99) / price)
+                if self.params.use_sizer:
+                    # 交由 cerebro 配置的仓位计算器决定股数
+                    size = self.getsizing(isbuy=True)
+                else:
+                    # 使用 99% 现金计算可买股数，预留手续费避免保证金不足
+                    cash = self.broker.get_cash()
+                    price = float(self.data.close[0])
+                    size = int((cash * 0.99) / price)
                 if size > 0:
                     self.order = self.buy(size=size)
         else:
@@ -0,0 +1,67 @@
+import math
+import numpy as np
+import backtrader as bt
+
+
+class ParkinsonVolatility(bt.Indicator):
+    """
+    Parkinson 波动率：基于最高/最低价，σ² = Σ ln(H/L)² / (4 ln2 · n)。
+    输出按 annual_days 年化（annual_days=1 即单根K线波动率）。
+    """
+    lines = ('vol',)
+    params = dict(period=20, annual_days=252)
+
+    def __init__(self):
+        self.addminperiod(self.p.period)
+
+    def next(self):
+        highs = np.asarray(self.data.high.get(size=self.p.period), dtype=float)
+        lows = np.asarray(self.data.low.get(size=self.p.period), dtype=float)
+        if (lows <= 0).any():
+            self.lines.vol[0] = float('nan')
+            return
+        var = np.mean(np.log(highs / lows) ** 2) / (4 * math.log(2))
+        self.lines.vol[0] = math.sqrt(var * self.p.annual_days)
+
+
+class BipowerVolatility(bt.Indicator):
+    """
+    双幂次变差波动率：σ² = (π/2) · Σ |r_i|·|r_{i-1}| / (n - 1)，r 为对数收益，对跳空不敏感。
+    输出按 annual_days 年化。
+    """
+    lines = ('vol',)
+    params = dict(period=20, annual_days=252)
+
+    def __init__(self):
+        self.addminperiod(self.p.period + 1)
+
+    def next(self):
+        closes = np.asarray(self.data.close.get(size=self.p.period + 1), dtype=float)
+        if (closes <= 0).any():
+            self.lines.vol[0] = float('nan')
+            return
+        rets = np.abs(np.diff(np.log(closes)))
+        var = (math.pi / 2) * np.sum(rets[1:] * rets[:-1]) / (len(rets) - 1)
+        self.lines.vol[0] = math.sqrt(var * self.p.annual_days)
+
+
+class EWMAVolatility(bt.Indicator):
+    """
+    EWMA 波动率（RiskMetrics）：σ²_t = λ·σ²_{t-1} + (1 - λ)·r_t²，首值取 r²。
+    输出按 annual_days 年化。
+    """
+    lines = ('vol',)
+    params = dict(lam=0.94, annual_days=252)
+
+    def __init__(self):
+        self.addminperiod(2)
+        self._var = None
+
+    def next(self):
+        prev, cur = float(self.data.close[-1]), float(self.data.close[0])
+        if prev <= 0 or cur <= 0:
+            self.lines.vol[0] = float('nan')
+            return
+        r2 = math.log(cur / prev) ** 2
+        self._var = r2 if self._var is None else self.p.lam * self._var + (1 - self.p.lam) * r2
+        self.lines.vol[0] = math.sqrt(self._var * self.p.annual_days)
@@ -0,0 +1,165 @@
+import math
+from collections import deque
+import numpy as np
+import backtrader as bt
+
+
+def adf_tstat(series) -> float:
+    """
+    无滞后项 ADF 回归 Δs_t = c + γ * s_{t-1} + ε 中 γ 的 t 统计量，越小越拒绝单位根（越平稳）。
+    样本不足或残差为 0 时返回 nan。
+    """
+    s = np.asarray(series, dtype=float)
+    lag = s[:-1]
+    diff = np.diff(s)
+    dof = len(diff) - 2
+    if dof <= 0:
+        return float('nan')
+    X = np.column_stack([np.ones_like(lag), lag])
+    coef = np.linalg.lstsq(X, diff, rcond=None)[0]
+    resid = diff - X @ coef
+    sigma2 = resid @ resid / dof
+    xtx = X.T @ X
+    if sigma2 <= 0 or np.linalg.det(xtx) == 0:
+        return float('nan')
+    return float(coef[1] / math.sqrt(sigma2 * np.linalg.inv(xtx)[1, 1]))
+
+
+class PairsTradingStrategy(bt.Strategy):
+    """
+    配对交易模板：symbols 传入两只标的 [y, x]（data0 为 y，data1 为 x）。
+    - 只在两只标的同一天都有K线时计算与下单（停牌、上市较晚或被过滤的日期跳过），窗口由最近 period 个共同交易日组成
+    - 对数价格滚动回归得到对冲比例 beta = cov(y, x) / var(x)
+    - 价差 spread = log(y) - beta * log(x)，按同一窗口计算 z-score
+    - 协整检验（Engle-Granger）：对窗口内价差做 ADF 检验，t 统计量低于 coint_tstat（默认 -3.34，约 5% 水平）才允许开仓；
+      None 不检验。已有持仓的平仓、止损不受影响
+    - |z| > entry_z 开仓（z > 0 做空 y 做多 x，反之亦然），|z| < exit_z 平仓，|z| > stop_z 止损
+    - 两条腿按市值对冲，合计占用 capital_pct 比例的账户资产
+    注意：本模板本身不处理 A 股卖空约束。run_backtest 传入 shortable（可融券标的表）时，券商对不在表中的空头腿
+    拒单或提示（见 backtest/broker.py）。开仓先下空头腿，被拒则不下多头腿；未传时空头腿无限制撮合。
+    融券费率、保证金与券源数量均未模拟，回测结果不代表 A 股账户可实现的收益。
+    """
+    params = dict(
+        period=60,
+        entry_z=2.0,
+        exit_z=0.5,
+        stop_z=4.0,
+        coint_tstat=-3.34,
+        capital_pct=0.9,
+        printlog=False,
+    )
+
+    def __init__(self):
+        self.y = self.datas[0]
+        self.x = self.datas[1]
+        self.beta = None
+        self.zscore = None
+        self.adf_t = None
+        self.orders = []
+        # 最近 period 个共同交易日的 (log y, log x)
+        self._window = deque(maxlen=self.params.period)
+
+    def log(self, txt):
+        if self.params.printlog:
+            dt = self.data.datetime.date(0)
+            print(f"{dt} - {txt}")
+
+    def notify_order(self, order):
+        if order.status in [order.Submitted, order.Accepted, order.Partial]:
+            # 部分成交时订单仍在撮合，继续等待终态
+            return
+        if order.status in [order.Completed]:
+            action = 'BUY' if order.isbuy() else 'SELL'
+            self.log(f"ORDER {action} {order.data._name} EXECUTED, price={order.executed.price:.4f}, size={order.executed.size}")
+        elif order.status in [order.Canceled, order.Margin, order.Rejected]:
+            self.log(f"ORDER {order.data._name} {order.getstatusname()}")
+        if order in self.orders:
+            self.orders.remove(order)
+
+    def notify_trade(self, trade):
+        if trade.isclosed:
+            self.log(f"TRADE {trade.data._name} PnL gross={trade.pnl:.2f} net={trade.pnlcomm:.2f}")
+
+    def _update_signal(self):
+        ys, xs = np.asarray(self._window, dtype=float).T
+        var_x = xs.var()
+        if var_x <= 0:
+            return False
+        self.beta = float(np.cov(ys, xs, bias=True)[0, 1] / var_x)
+        spread = ys - self.beta * xs
+        std = spread.std()
+        if std <= 0:
+            return False
+        self.zscore = float((spread[-1] - spread.mean()) / std)
+        self.adf_t = adf_tstat(spread)
+        return True
+
+    def _cointegrated(self):
+        if self.params.coint_tstat is None:
+            return True
+        return not math.isnan(self.adf_t) and self.adf_t < self.params.coint_tstat
+
+    def _open(self, direction):
+        # direction=+1 做多价差（买 y 卖 x），-1 做空价差
+        notional = self.broker.getvalue() * self.params.capital_pct
+        price_y = float(self.y.close[0])
+        price_x = float(self.x.close[0])
+        # 对数价格回归下 beta 即收益敞口比例，按 1 : |beta| 的市值分配两条腿
+        hedge = abs(self.beta)
+        value_y = notional / (1 + hedge)
+        value_x = notional - value_y
+        size_y = int(value_y / price_y)
+        size_x = int(value_x / price_x)
+        if size_y <= 0 or size_x <= 0:
+            return
+        if direction > 0:
+            legs = [(self.x, size_x, self.sell), (self.y, size_y, self.buy)]
+        else:
+            legs = [(self.y, size_y, self.sell), (self.x, size_x, self.buy)]
+        # 先下空头腿：下单时即被券商拒绝（如不在可融券表中）则不再下多头腿，避免单腿开仓
+        for data, size, submit in legs:
+            order = submit(data=data, size=size)
+            self.orders.append(order)
+            if order.status in [order.Rejected]:
+                self.log(f"OPEN aborted, {data._name} rejected")
+                return
+        self.log(f"OPEN spread dir={direction}, z={self.zscore:.2f}, beta={self.beta:.3f}, adf_t={self.adf_t:.2f}")
+
+    def _close_all(self, reason):
+        for data in (self.y, self.x):
+            order = self.close(data=data)
+            if order is not None:
+                self.orders.append(order)
+        self.log(f"CLOSE spread ({reason}), z={self.zscore:.2f}")
+
+    def next(self):
+        # 任一腿当天没有K线（停牌等）时其数据停在上一交易日，跳过，避免窗口错位与按旧价下单
+        if self.y.datetime[0] != self.x.datetime[0]:
+            return
+        price_y = float(self.y.close[0])
+        price_x = float(self.x.close[0])
+        if price_y <= 0 or price_x <= 0:
+            return
+        self._window.append((math.log(price_y), math.log(price_x)))
+        if len(self._window) < self.params.period or self.orders:
+            return
+        if not self._update_signal() or math.isnan(self.zscore):
+            return
+
+        pos_y = self.getposition(self.y).size
+        pos_x = self.getposition(self.x).size
+        if bool(pos_y) != bool(pos_x):
+            # 只有一条腿成交（另一条被拒单或未成交），先平掉单腿敞口
+            self._close_all('orphan leg')
+        elif not pos_y:
+            if not self._cointegrated():
+                return
+            if self.zscore > self.params.entry_z:
+                self._open(-1)
+            elif self.zscore < -self.params.entry_z:
+                self._open(1)
+        else:
+            if abs(self.zscore) < self.params.exit_z:
+                self._close_all('revert')
+            elif abs(self.zscore) > self.params.stop_z:
+                self._close_all('stop')
@@ -0,0 +1,74 @@
+import unittest
+
+import backtrader as bt
+import pandas as pd
+
+from backtest import AShareBroker
+
+
+class _FixedOrders(bt.Strategy):
+    # 第 1 根买 4000 股（40%），第 2 根再买 2000 股（合计 60%），第 3 根全部卖出
+    params = dict(plan={1: 4000, 2: 2000, 3: -4000})
+
+    def __init__(self):
+        self.statuses = []
+
+    def notify_order(self, order):
+        if order.status in [order.Completed, order.Rejected]:
+            self.statuses.append((len(self), order.getstatusname(), order.created.size))
+
+    def next(self):
+        size = self.p.plan.get(len(self), 0)
+        if size > 0:
+            self.buy(size=size)
+        elif size < 0:
+            self.sell(size=-size)
+
+
+def _run(max_gross_pct=None, plan=None, **broker_kwargs):
+    idx = pd.date_range('2024-01-01', periods=5, freq='D', name='datetime')
+    df = pd.DataFrame({'open': 10.0, 'high': 10.0, 'low': 10.0, 'close': 10.0, 'volume': 1e6}, index=idx)
+    cerebro = bt.Cerebro()
+    cerebro.adddata(bt.feeds.PandasData(dataname=df), name='600000')
+    cerebro.addstrategy(_FixedOrders, **({'plan': plan} if plan else {}))
+    cerebro.broker = AShareBroker(max_gross_pct=max_gross_pct, **broker_kwargs)
+    cerebro.broker.setcash(100000.0)
+    cerebro.broker.set_coc(True)
+    strat = cerebro.run()[0]
+    return strat.statuses, cerebro.broker
+
+
+class GrossCapTest(unittest.TestCase):
+    def test_explicit_size_order_over_cap_is_rejected(self):
+        statuses, broker = _run(0.5)
+        self.assertEqual([s[1] for s in statuses], ['Completed', 'Rejected', 'Completed'])
+        self.assertEqual(broker.gross_rejected, 1)
+
+    def test_no_cap(self):
+        statuses, broker = _run(None)
+        self.assertEqual([s[1] for s in statuses], ['Completed', 'Completed', 'Completed'])
+        self.assertEqual(broker.gross_rejected, 0)
+
+
+class ShortableTest(unittest.TestCase):
+    # 买 1000、卖 1000 平多、再卖 1000 开空
+    PLAN = {1: 1000, 2: -1000, 3: -1000}
+
+    def test_short_open_outside_table_is_rejected(self):
+        statuses, broker = _run(plan=self.PLAN, shortable=['601398'])
+        self.assertEqual([s[1] for s in statuses], ['Completed', 'Completed', 'Rejected'])
+        self.assertEqual(broker.short_violations, 1)
+
+    def test_shortable_symbol_passes(self):
+        statuses, broker = _run(plan=self.PLAN, shortable=['600000.SH'])
+        self.assertEqual([s[1] for s in statuses], ['Completed', 'Completed', 'Completed'])
+        self.assertEqual(broker.short_violations, 0)
+
+    def test_warn_mode_fills(self):
+        statuses, broker = _run(plan=self.PLAN, shortable=[], short_check='warn')
+        self.assertEqual([s[1] for s in statuses], ['Completed', 'Completed', 'Completed'])
+        self.assertEqual(broker.short_violations, 1)
+
+
+if __name__ == '__main__':
+    unittest.main()
@@ -0,0 +1,74 @@
+import os
+import tempfile
+import unittest
+
+import pandas as pd
+
+from backtest import filter_outlier_bars
+from backtest.engine import _append_audit
+
+
+def _bars(closes, highs=None, lows=None):
+    idx = pd.date_range('2024-01-01', periods=len(closes), freq='D', name='datetime')
+    return pd.DataFrame({
+        'Open': closes,
+        'High': highs or closes,
+        'Low': lows or closes,
+        'Close': closes,
+        'Volume': 1000.0,
+    }, index=idx)
+
+
+class FilterOutlierBarsTest(unittest.TestCase):
+    def test_invalid_bars(self):
+        df = _bars([10.0, 0.0, 10.0, 10.0], highs=[10.0, 10.0, 9.0, 10.0], lows=[10.0, 0.0, 9.5, 10.0])
+        kept, audit = filter_outlier_bars(df)
+        self.assertEqual(len(kept), 2)
+        self.assertEqual(audit['reason'].tolist(), ['non_positive_price', 'high_below_low'])
+
+    def test_invalid_checks_can_be_disabled(self):
+        df = _bars([10.0, 10.0], highs=[10.0, 9.0], lows=[10.0, 9.5])
+        kept, audit = filter_outlier_bars(df, check_invalid=False)
+        self.assertEqual(len(kept), 2)
+        self.assertTrue(audit.empty)
+
+    def test_isolated_spike(self):
+        df = _bars([10.0, 10.0, 20.0, 10.0, 10.0])
+        kept, audit = filter_outlier_bars(df, max_jump_pct=25)
+        self.assertEqual(audit.index.tolist(), [pd.Timestamp('2024-01-03')])
+        self.assertEqual(audit['reason'].tolist(), ['price_jump'])
+        self.assertEqual(len(kept), 4)
+
+    def test_level_shift_is_not_a_spike(self):
+        df = _bars([10.0, 10.0, 20.0, 20.0, 20.0])
+        _, audit = filter_outlier_bars(df, max_jump_pct=25)
+        self.assertTrue(audit.empty)
+
+    def test_first_and_last_bars_are_not_spike_checked(self):
+        df = _bars([20.0, 10.0, 10.0, 10.0, 20.0])
+        kept, audit = filter_outlier_bars(df, max_jump_pct=25)
+        self.assertTrue(audit.empty)
+        self.assertEqual(len(kept), 5)
+
+    def test_spike_neighbours_skip_invalid_bars(self):
+        # 非正价格的K线先被剔除，尖刺按前后有效K线判断
+        df = _bars([10.0, 0.0, 20.0, 10.0])
+        _, audit = filter_outlier_bars(df, max_jump_pct=25)
+        self.assertEqual(audit['reason'].tolist(), ['non_positive_price', 'price_jump'])
+
+
+class AppendAuditTest(unittest.TestCase):
+    def test_repeated_loads_are_deduplicated(self):
+        _, audit = filter_outlier_bars(_bars([10.0, 10.0, 20.0, 10.0, 10.0]), max_jump_pct=25)
+        with tempfile.TemporaryDirectory() as tmp:
+            path = os.path.join(tmp, 'audit.csv')
+            _append_audit(path, '000001', audit)
+            _append_audit(path, '000001', audit)
+            _append_audit(path, '600000', audit)
+            rows = pd.read_csv(path, dtype={'symbol': str})
+        self.assertEqual(rows['symbol'].tolist(), ['000001', '600000'])
+        self.assertEqual(rows['reason'].tolist(), ['price_jump', 'price_jump'])
+
+
+if __name__ == '__main__':
+    unittest.main()
@@ -0,0 +1,71 @@
+import datetime
+import unittest
+
+import backtrader as bt
+import pandas as pd
+
+from backtest import AShareBroker, PriceLimitFiller
+
+
+# 600000 为主板 10%：第 2 根收盘 11.00 为涨停（前收 10.00），第 3 根开盘 11.50 未涨停（涨停价 12.10）
+BARS = [
+    ('2024-01-02', 10.00, 10.00, 10.00, 10.00),
+    ('2024-01-03', 10.50, 11.00, 10.50, 11.00),
+    ('2024-01-04', 11.50, 11.80, 11.40, 11.60),
+    ('2024-01-05', 11.80, 12.00, 11.70, 11.90),
+    ('2024-01-08', 11.90, 12.00, 11.80, 11.90),
+]
+
+
+class _BuyOnSecondBar(bt.Strategy):
+    def __init__(self):
+        self.fills = []
+
+    def notify_order(self, order):
+        if order.status in [order.Completed]:
+            self.fills.append((bt.num2date(order.executed.dt).date(), order.executed.price))
+
+    def next(self):
+        if len(self) == 2:
+            self.buy(size=100)
+
+
+def _run(bars, broker=None):
+    df = pd.DataFrame(bars, columns=['datetime', 'open', 'high', 'low', 'close'])
+    df['datetime'] = pd.to_datetime(df['datetime'])
+    df['volume'] = 1e6
+    cerebro = bt.Cerebro()
+    cerebro.adddata(bt.feeds.PandasData(dataname=df.set_index('datetime')), name='600000')
+    cerebro.addstrategy(_BuyOnSecondBar)
+    if broker is not None:
+        cerebro.broker = broker
+    cerebro.broker.setcash(100000.0)
+    cerebro.broker.set_coc(True)
+    filler = PriceLimitFiller(coc=True)
+    cerebro.broker.set_filler(filler)
+    strat = cerebro.run()[0]
+    return strat.fills, filler.blocked
+
+
+class PriceLimitFillTest(unittest.TestCase):
+    def test_coc_order_fills_at_close_when_not_at_limit(self):
+        bars = list(BARS)
+        bars[1] = ('2024-01-03', 10.50, 10.90, 10.50, 10.80)
+        fills, blocked = _run(bars, AShareBroker())
+        self.assertEqual(fills, [(datetime.date(2024, 1, 3), 10.80)])
+        self.assertEqual(blocked, 0)
+
+    def test_limit_up_coc_order_fills_at_next_open_with_ashare_broker(self):
+        fills, blocked = _run(BARS, AShareBroker())
+        self.assertEqual(fills, [(datetime.date(2024, 1, 4), 11.50)])
+        self.assertEqual(blocked, 1)
+
+    def test_limit_up_coc_order_with_filler_only_fills_one_bar_later(self):
+        # 只用 filler 时 t+1 的撮合轮次用于拦截，关闭 coc 后在 t+2 开盘成交
+        fills, blocked = _run(BARS)
+        self.assertEqual(fills, [(datetime.date(2024, 1, 5), 11.80)])
+        self.assertEqual(blocked, 1)
+
+
+if __name__ == '__main__':
+    unittest.main()
@@ -0,0 +1,53 @@
+import unittest
+
+from backtest import board_of, limit_pct_of, limit_prices, venue_of
+
+
+class LimitPricesTest(unittest.TestCase):
+    def test_half_up_ties(self):
+        # 浮点乘积为 1.4849999... / 1.9949999...，按交易所规则应进位
+        self.assertEqual(limit_prices(1.65, 0.10), (1.49, 1.82))
+        self.assertEqual(limit_prices(1.9, 0.05), (1.81, 2.00))
+
+    def test_regular_prices(self):
+        self.assertEqual(limit_prices(10.00, 0.10), (9.00, 11.00))
+        self.assertEqual(limit_prices(12.34, 0.20), (9.87, 14.81))
+        self.assertEqual(limit_prices(3.33, 0.30), (2.33, 4.33))
+
+
+class LimitPctTest(unittest.TestCase):
+    def test_boards(self):
+        self.assertEqual(limit_pct_of('600000'), 0.10)
+        self.assertEqual(limit_pct_of('000001.SZ'), 0.10)
+        self.assertEqual(limit_pct_of('300750'), 0.20)
+        self.assertEqual(limit_pct_of('688981'), 0.20)
+        self.assertEqual(limit_pct_of('830799'), 0.30)
+        self.assertEqual(limit_pct_of('920001'), 0.30)
+
+    def test_st_and_explicit_map(self):
+        self.assertEqual(limit_pct_of('600001', st_symbols=['600001']), 0.05)
+        self.assertEqual(limit_pct_of('600001', st_symbols=['600001'], limit_pct_map={'600001': 0.10}), 0.10)
+
+    def test_unknown_board_uses_main_board_limit(self):
+        self.assertEqual(limit_pct_of('999999'), 0.10)
+
+
+class BoardTest(unittest.TestCase):
+    def test_board_of(self):
+        self.assertEqual(board_of('600519'), ('SSE', 'MAIN'))
+        self.assertEqual(board_of('688001.SH'), ('SSE', 'STAR'))
+        self.assertEqual(board_of('900901'), ('SSE', 'B'))
+        self.assertEqual(board_of('000001'), ('SZSE', 'MAIN'))
+        self.assertEqual(board_of('301001'), ('SZSE', 'CHINEXT'))
+        self.assertEqual(board_of('200002'), ('SZSE', 'B'))
+        self.assertEqual(board_of('920001'), ('BSE', 'BSE'))
+        self.assertEqual(board_of('430047'), ('BSE', 'BSE'))
+        self.assertEqual(board_of('999999'), ('UNKNOWN', 'UNKNOWN'))
+
+    def test_venue_of(self):
+        self.assertEqual(venue_of('601398'), 'SSE')
+        self.assertEqual(venue_of('300750'), 'SZSE')
+
+
+if __name__ == '__main__':
+    unittest.main()